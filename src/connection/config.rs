@@ -0,0 +1,55 @@
+//! Connection-level tuning knobs applied to every `TcpStream` the pool opens.
+
+use std::time::Duration;
+
+use crate::connection::retry::RetryPolicy;
+
+/// TCP-level settings applied at connect time.
+#[derive(Debug, Clone)]
+pub struct ConnectionConfig {
+    pub keepalive: KeepaliveConfig,
+    /// Disables Nagle's algorithm so small request/response PDUs aren't
+    /// delayed waiting to be coalesced.
+    pub nodelay: bool,
+    /// Maximum time to wait for a single PDU write, mirroring the
+    /// `write_timeout` a plain socket would expose.
+    pub write_timeout: Duration,
+    /// Maximum time to wait for a single PDU read, mirroring the
+    /// `read_timeout` a plain socket would expose.
+    pub read_timeout: Duration,
+    /// Bounded retry/backoff applied to transient failures of a single PDU
+    /// exchange.
+    pub retry: RetryPolicy,
+}
+
+impl Default for ConnectionConfig {
+    fn default() -> Self {
+        Self {
+            keepalive: KeepaliveConfig::default(),
+            nodelay: true,
+            write_timeout: Duration::from_secs(2),
+            read_timeout: Duration::from_secs(2),
+            retry: RetryPolicy::default(),
+        }
+    }
+}
+
+/// TCP keepalive tuning, the equivalent of Winsock's `SIO_KEEPALIVE_VALS`:
+/// how long the link may sit idle before the first probe, how often probes
+/// repeat, and how many unanswered probes it takes to declare the link dead.
+#[derive(Debug, Clone, Copy)]
+pub struct KeepaliveConfig {
+    pub idle: Duration,
+    pub interval: Duration,
+    pub retries: u32,
+}
+
+impl Default for KeepaliveConfig {
+    fn default() -> Self {
+        Self {
+            idle: Duration::from_secs(30),
+            interval: Duration::from_secs(10),
+            retries: 3,
+        }
+    }
+}
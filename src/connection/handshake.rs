@@ -0,0 +1,221 @@
+//! ISO-on-TCP connection setup (COTP `CR`/`CC`) followed by the S7
+//! `Setup Communication` job/ack-data exchange that negotiates `pdu_length`.
+//! Run once when a pooled connection is created, and re-run by
+//! [`super::pool::Manager::recycle`] whenever a PLC restart has wiped out
+//! the previously negotiated state.
+
+use std::io::Cursor;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+use crate::connection::config::ConnectionConfig;
+use crate::connection::tcp::exchange_buffer;
+use crate::errors::{Error, IsoError};
+use crate::s7_protocol::header::S7ProtocolHeader;
+use crate::s7_protocol::proto::{ProtoRead, ProtoWrite};
+
+const SETUP_COMMUNICATION: u8 = 0xF0;
+
+/// The outcome of a successful handshake: what the PLC agreed to negotiate.
+pub(crate) struct Handshake {
+    pub(crate) pdu_length: u16,
+}
+
+/// Establishes the COTP connection and negotiates the S7 PDU length against
+/// a freshly opened `stream`.
+pub(crate) async fn negotiate(
+    stream: &mut TcpStream,
+    local_tsap: u16,
+    remote_tsap: u16,
+    config: &ConnectionConfig,
+) -> Result<Handshake, Error> {
+    connect_request(stream, local_tsap, remote_tsap, config).await?;
+    setup_communication(stream, config).await
+}
+
+/// Sends a COTP Connection Request (`CR`) carrying the calling/called TSAPs
+/// and waits for the matching Connection Confirm (`CC`).
+async fn connect_request(
+    stream: &mut TcpStream,
+    local_tsap: u16,
+    remote_tsap: u16,
+    config: &ConnectionConfig,
+) -> Result<(), Error> {
+    // `Cursor<Vec<u8>>` also implements tokio's `AsyncWrite`, so with both
+    // `ProtoWrite` and `AsyncWriteExt` in scope for the `stream.write_all`
+    // calls below, `.write_u8(...)`/`.write_u16(...)` on a cursor is
+    // ambiguous; disambiguate with UFCS.
+    let mut cotp = Cursor::new(Vec::new());
+    ProtoWrite::write_u8(&mut cotp, 0x0E); // COTP header length (following this field)
+    ProtoWrite::write_u8(&mut cotp, 0xE0); // COTP PDU type: Connection Request
+    ProtoWrite::write_u16(&mut cotp, 0x0000); // destination reference, unknown yet
+    ProtoWrite::write_u16(&mut cotp, 0x000F); // source reference
+    ProtoWrite::write_u8(&mut cotp, 0x00); // class + options: class 0, no extras
+    ProtoWrite::write_u8(&mut cotp, 0xC1); // parameter code: calling TSAP
+    ProtoWrite::write_u8(&mut cotp, 0x02); // parameter length
+    ProtoWrite::write_u16(&mut cotp, local_tsap);
+    ProtoWrite::write_u8(&mut cotp, 0xC2); // parameter code: called TSAP
+    ProtoWrite::write_u8(&mut cotp, 0x02);
+    ProtoWrite::write_u16(&mut cotp, remote_tsap);
+
+    let mut tpkt = Vec::with_capacity(4 + cotp.get_ref().len());
+    tpkt.push(0x03);
+    tpkt.push(0x00);
+    tpkt.extend_from_slice(&((4 + cotp.get_ref().len()) as u16).to_be_bytes());
+    tpkt.extend_from_slice(cotp.get_ref());
+
+    timeout(config.write_timeout, stream.write_all(&tpkt))
+        .await
+        .map_err(|_| Error::DataExchangeTimedOut)?
+        .map_err(|_| Error::ISORequest(IsoError::SendPacket))?;
+
+    let mut tpkt_header = [0u8; 4];
+    timeout(config.read_timeout, stream.read_exact(&mut tpkt_header))
+        .await
+        .map_err(|_| Error::DataExchangeTimedOut)?
+        .map_err(|_| Error::ISOResponse(IsoError::RecvPacket))?;
+    let total_len = u16::from_be_bytes([tpkt_header[2], tpkt_header[3]]) as usize;
+    if total_len < 4 {
+        return Err(Error::ISOResponse(IsoError::ShortPacket));
+    }
+    let mut rest = vec![0u8; total_len - 4];
+    timeout(config.read_timeout, stream.read_exact(&mut rest))
+        .await
+        .map_err(|_| Error::DataExchangeTimedOut)?
+        .map_err(|_| Error::ISOResponse(IsoError::RecvPacket))?;
+
+    // rest[0] is the COTP header length; the PDU type follows it.
+    match rest.get(1) {
+        Some(0xD0) => Ok(()), // Connection Confirm
+        _ => Err(Error::ISOResponse(IsoError::Connect)),
+    }
+}
+
+/// Sends the S7 `Setup Communication` job request and returns the PDU length
+/// the PLC agreed to.
+async fn setup_communication(stream: &mut TcpStream, config: &ConnectionConfig) -> Result<Handshake, Error> {
+    let mut params = Cursor::new(Vec::new());
+    ProtoWrite::write_u8(&mut params, SETUP_COMMUNICATION);
+    ProtoWrite::write_u8(&mut params, 0x00); // reserved
+    ProtoWrite::write_u16(&mut params, 0x0001); // max AmQ calling
+    ProtoWrite::write_u16(&mut params, 0x0001); // max AmQ called
+    ProtoWrite::write_u16(&mut params, 960); // requested PDU length
+
+    let mut pdu_reference = 1;
+    let header = S7ProtocolHeader::build_request(&mut pdu_reference, params.get_ref().len() as u16, 0);
+    let mut request_buf = Cursor::new(Vec::new());
+    header.encode(&mut request_buf);
+    ProtoWrite::write_bytes(&mut request_buf, params.get_ref());
+
+    let mut request = request_buf.into_inner();
+    let exchanged = exchange_buffer(stream, &mut request, config).await?;
+    let mut response = Cursor::new(exchanged);
+
+    let header = S7ProtocolHeader::decode(&mut response)?.is_ack()?;
+    if header.has_error() {
+        return Err(Error::RequestNotAcknowledged);
+    }
+
+    let _function_code = ProtoRead::read_u8(&mut response)?;
+    let _reserved = ProtoRead::read_u8(&mut response)?;
+    let _max_amq_calling = ProtoRead::read_u16(&mut response)?;
+    let _max_amq_called = ProtoRead::read_u16(&mut response)?;
+    let pdu_length = ProtoRead::read_u16(&mut response)?;
+
+    Ok(Handshake { pdu_length })
+}
+
+/// How long [`is_alive`] waits for its peek to see something before
+/// concluding there's nothing pending (the "alive and idle" case). Kept tiny:
+/// this only needs to catch a socket that's already readable (closed, or
+/// holding a stale response), not to detect slow PLCs.
+const LIVENESS_PEEK_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(5);
+
+/// A cheap liveness check for a pooled connection: a non-consuming peek that
+/// should see nothing. A PLC that vanished surfaces either as an orderly
+/// close (`Ok(0)`) or an I/O error; an idle-but-live link times out on the
+/// peek, since there is nothing for the peer to have sent.
+///
+/// Uses `TcpStream::peek`, not `try_read`: `try_read` is a real non-blocking
+/// `recv` that consumes whatever bytes are pending, so if a response byte
+/// was already sitting in the socket when the connection was recycled,
+/// `try_read` would silently eat it and desync every exchange after. `peek`
+/// leaves the bytes in the kernel buffer for the next real read.
+///
+/// This only checks that the TCP socket is still up; it says nothing about
+/// whether the negotiated S7 session is still valid, which is why
+/// [`super::pool::Manager::recycle`] follows it with [`revalidate_session`].
+pub(crate) async fn is_alive(stream: &TcpStream) -> bool {
+    let mut probe = [0u8; 1];
+    matches!(
+        timeout(LIVENESS_PEEK_TIMEOUT, stream.peek(&mut probe)).await,
+        Err(_) // no data pending within the probe window: still alive
+    )
+}
+
+/// Re-runs `Setup Communication` against an already-connected, already
+/// TCP-alive stream and returns the PDU length the PLC agreed to.
+///
+/// A PLC restart can leave the TCP socket open while wiping the session
+/// state it negotiated, so a connection can pass [`is_alive`] and still be
+/// stale — surfacing downstream as `ResponseDoesNotBelongToCurrentPDU` or an
+/// ISO error on the first real exchange. Running the handshake's
+/// application-level request again is the only way to confirm the PLC still
+/// agrees on a `pdu_length`; an error here means the session (or the link
+/// itself) is gone and the connection must be rebuilt from scratch.
+pub(crate) async fn revalidate_session(
+    stream: &mut TcpStream,
+    config: &ConnectionConfig,
+) -> Result<u16, Error> {
+    setup_communication(stream, config).await.map(|h| h.pdu_length)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    async fn loopback_pair() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = TcpStream::connect(addr);
+        let (server, client) = tokio::join!(
+            async { listener.accept().await.unwrap().0 },
+            async { client.await.unwrap() }
+        );
+        (server, client)
+    }
+
+    #[tokio::test]
+    async fn idle_but_open_connection_is_alive() {
+        let (_server, client) = loopback_pair().await;
+        assert!(is_alive(&client).await);
+    }
+
+    #[tokio::test]
+    async fn orderly_close_is_not_alive() {
+        let (server, client) = loopback_pair().await;
+        drop(server);
+        assert!(!is_alive(&client).await);
+    }
+
+    #[tokio::test]
+    async fn a_pending_byte_is_peeked_not_consumed() {
+        let (mut server, client) = loopback_pair().await;
+        server.write_all(&[0x42]).await.unwrap();
+
+        // A byte sitting unread makes the link look suspect (treated the
+        // same as "not alive", which forces a reconnect upstream)...
+        assert!(!is_alive(&client).await);
+
+        // ...but the byte itself must still be there for the next real
+        // reader, which is the whole point of using `peek` over `try_read`.
+        let mut probe = [0u8; 1];
+        let n = client.peek(&mut probe).await.unwrap();
+        assert_eq!((n, probe[0]), (1, 0x42));
+    }
+}
@@ -0,0 +1,12 @@
+//! Transport-level concerns: the ISO-on-TCP link to a PLC and, above it, the
+//! pooled connection management built on `deadpool`.
+
+pub mod config;
+mod handshake;
+pub mod pool;
+pub mod retry;
+pub mod tcp;
+
+pub use config::{ConnectionConfig, KeepaliveConfig};
+pub use pool::{Manager, PlcConnection};
+pub use retry::RetryPolicy;
@@ -0,0 +1,110 @@
+//! Bounded retry/backoff for transient failures of a single PDU exchange.
+
+use std::time::Duration;
+
+use tokio::net::TcpStream;
+
+use crate::connection::config::ConnectionConfig;
+use crate::connection::tcp::exchange_buffer;
+use crate::errors::{Error, IsoError};
+
+/// How many times, and with what backoff, a transient failure during a PDU
+/// exchange gets retried under a fresh PDU number before giving up.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn backoff_for(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt);
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+
+    /// Whether a failure is worth retrying in place on the same stream, as
+    /// opposed to a definitive protocol/PLC-side error that retrying can't
+    /// fix, or a timeout that has already left the stream mid-frame.
+    ///
+    /// [`Error::DataExchangeTimedOut`] is deliberately excluded:
+    /// `exchange_buffer` shuts the stream down whenever a timeout fires, since
+    /// `read_exact` isn't cancel-safe and bytes already pulled off the wire
+    /// are gone. Retrying on that stream would write a fresh request next to
+    /// a dangling partial response, so a timeout is handed straight back to
+    /// the caller instead.
+    fn is_transient(err: &Error) -> bool {
+        matches!(
+            err,
+            Error::ISOResponse(IsoError::RecvPacket) | Error::ISOResponse(IsoError::ShortPacket)
+        )
+    }
+}
+
+/// Runs a single PDU exchange, retrying under a fresh PDU reference on
+/// transient failures for up to `config.retry.max_attempts` attempts in all
+/// (the first one included), with exponential
+/// backoff between attempts. `build_request` is handed the PDU reference to
+/// embed in the S7 header for that attempt; a retry that exhausts its budget
+/// returns the last underlying [`Error`].
+pub(crate) async fn exchange_with_retry(
+    conn: &mut TcpStream,
+    pdu_number: &mut u16,
+    config: &ConnectionConfig,
+    mut build_request: impl FnMut(u16) -> Vec<u8>,
+) -> Result<Vec<u8>, Error> {
+    let mut attempt = 0;
+    loop {
+        let mut request = build_request(*pdu_number);
+        match exchange_buffer(conn, &mut request, config).await {
+            Ok(data) => return Ok(data),
+            Err(e) if attempt + 1 < config.retry.max_attempts && RetryPolicy::is_transient(&e) => {
+                tokio::time::sleep(config.retry.backoff_for(attempt)).await;
+                attempt += 1;
+                *pdu_number = pdu_number.wrapping_add(1);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_exponentially_and_caps() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff_for(0), Duration::from_millis(50));
+        assert_eq!(policy.backoff_for(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff_for(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff_for(20), policy.max_backoff);
+    }
+
+    #[test]
+    fn only_recv_and_short_packet_errors_are_transient() {
+        assert!(RetryPolicy::is_transient(&Error::ISOResponse(
+            IsoError::RecvPacket
+        )));
+        assert!(RetryPolicy::is_transient(&Error::ISOResponse(
+            IsoError::ShortPacket
+        )));
+
+        // A timeout has already torn the stream down in `exchange_buffer`,
+        // so it must not be retried in place on the same connection.
+        assert!(!RetryPolicy::is_transient(&Error::DataExchangeTimedOut));
+        assert!(!RetryPolicy::is_transient(
+            &Error::ResponseDoesNotBelongToCurrentPDU
+        ));
+    }
+}
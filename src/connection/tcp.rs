@@ -0,0 +1,133 @@
+//! ISO-on-TCP (RFC 1006) framing: every S7 PDU we hand to [`exchange_buffer`]
+//! gets wrapped in a TPKT header and a COTP data header before being sent,
+//! and unwrapped the same way on the way back.
+
+use std::io::ErrorKind;
+use std::net::SocketAddr;
+
+use socket2::{SockRef, TcpKeepalive};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+use crate::connection::config::ConnectionConfig;
+use crate::errors::{Error, IsoError};
+
+const TPKT_HEADER_LEN: usize = 4;
+const COTP_DATA_HEADER_LEN: usize = 3;
+
+/// Opens a TCP connection to `addr` and applies `config`'s socket options
+/// before handing it back.
+pub(crate) async fn connect(addr: SocketAddr, config: &ConnectionConfig) -> Result<TcpStream, Error> {
+    let stream = TcpStream::connect(addr)
+        .await
+        .map_err(|e| Error::Connection(e.to_string()))?;
+    apply_socket_options(&stream, config)?;
+    Ok(stream)
+}
+
+/// Applies TCP keepalive and `TCP_NODELAY` to an already-connected stream, so
+/// a powered-off or network-partitioned PLC surfaces as a dropped connection
+/// within a few probe intervals instead of a stalled `exchange_buffer`.
+pub(crate) fn apply_socket_options(stream: &TcpStream, config: &ConnectionConfig) -> Result<(), Error> {
+    let sock_ref = SockRef::from(stream);
+
+    let keepalive = TcpKeepalive::new()
+        .with_time(config.keepalive.idle)
+        .with_interval(config.keepalive.interval)
+        .with_retries(config.keepalive.retries);
+    sock_ref
+        .set_tcp_keepalive(&keepalive)
+        .map_err(|e| Error::Connection(format!("failed to set TCP keepalive: {e}")))?;
+
+    sock_ref
+        .set_nodelay(config.nodelay)
+        .map_err(|e| Error::Connection(format!("failed to set TCP_NODELAY: {e}")))?;
+
+    Ok(())
+}
+
+/// Sends `payload` as a single, complete TPDU and returns the S7 PDU found in
+/// the reply (TPKT/COTP framing stripped). Each send and receive is bounded
+/// by `config`'s write/read timeout; either one lapsing yields
+/// [`Error::DataExchangeTimedOut`].
+///
+/// `AsyncReadExt::read_exact` is not cancel-safe: if a `timeout` fires
+/// mid-read, whatever bytes it already pulled out of the kernel buffer are
+/// gone when the future is dropped, leaving `conn` mid-frame. Resuming on
+/// the same stream would hand the next reader misaligned bytes, so any
+/// timeout here shuts the stream down rather than letting the caller retry
+/// in place; see [`super::retry::exchange_with_retry`].
+pub(crate) async fn exchange_buffer(
+    conn: &mut TcpStream,
+    payload: &mut Vec<u8>,
+    config: &ConnectionConfig,
+) -> Result<Vec<u8>, Error> {
+    let mut frame = Vec::with_capacity(TPKT_HEADER_LEN + COTP_DATA_HEADER_LEN + payload.len());
+    let total_len = (TPKT_HEADER_LEN + COTP_DATA_HEADER_LEN + payload.len()) as u16;
+    frame.push(0x03); // TPKT version
+    frame.push(0x00); // reserved
+    frame.extend_from_slice(&total_len.to_be_bytes());
+    frame.push(0x02); // COTP header length (following this field)
+    frame.push(0xF0); // COTP PDU type: DT Data
+    frame.push(0x80); // TPDU number + EOT flag
+    frame.append(payload);
+
+    match timeout(config.write_timeout, conn.write_all(&frame)).await {
+        Err(_) => {
+            shut_down(conn).await;
+            return Err(Error::DataExchangeTimedOut);
+        }
+        Ok(result) => result.map_err(|e| map_io_error(e, IsoError::SendPacket))?,
+    }
+
+    let mut tpkt_header = [0u8; TPKT_HEADER_LEN];
+    match timeout(config.read_timeout, conn.read_exact(&mut tpkt_header)).await {
+        Err(_) => {
+            shut_down(conn).await;
+            return Err(Error::DataExchangeTimedOut);
+        }
+        Ok(result) => {
+            result.map_err(|e| map_io_error(e, IsoError::RecvPacket))?;
+        }
+    }
+    let total_len = u16::from_be_bytes([tpkt_header[2], tpkt_header[3]]) as usize;
+    if total_len < TPKT_HEADER_LEN + COTP_DATA_HEADER_LEN {
+        return Err(Error::ISOResponse(IsoError::ShortPacket));
+    }
+
+    let mut rest = vec![0u8; total_len - TPKT_HEADER_LEN];
+    match timeout(config.read_timeout, conn.read_exact(&mut rest)).await {
+        Err(_) => {
+            shut_down(conn).await;
+            return Err(Error::DataExchangeTimedOut);
+        }
+        Ok(result) => {
+            result.map_err(|e| map_io_error(e, IsoError::RecvPacket))?;
+        }
+    }
+
+    Ok(rest.split_off(COTP_DATA_HEADER_LEN))
+}
+
+/// Best-effort teardown of a stream left mid-frame by a cancelled read/write,
+/// so it surfaces as dead (and gets rebuilt by [`super::pool::Manager`])
+/// instead of quietly carrying stale bytes into the next exchange.
+async fn shut_down(conn: &mut TcpStream) {
+    let _ = conn.shutdown().await;
+}
+
+/// Distinguishes a keepalive-detected dead link from an ordinary protocol
+/// error, so callers can tell "the PLC vanished" from "the PLC replied with
+/// garbage".
+fn map_io_error(e: std::io::Error, protocol_kind: IsoError) -> Error {
+    match e.kind() {
+        ErrorKind::ConnectionReset
+        | ErrorKind::ConnectionAborted
+        | ErrorKind::BrokenPipe
+        | ErrorKind::NotConnected
+        | ErrorKind::TimedOut => Error::Connection(e.to_string()),
+        _ if matches!(protocol_kind, IsoError::SendPacket) => Error::ISORequest(protocol_kind),
+        _ => Error::ISOResponse(protocol_kind),
+    }
+}
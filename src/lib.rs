@@ -0,0 +1,6 @@
+//! A pure-Rust, async client for Siemens S7 PLCs (S7-300/400/1200/1500) over
+//! ISO-on-TCP.
+
+pub mod connection;
+pub mod errors;
+pub mod s7_protocol;
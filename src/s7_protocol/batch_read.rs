@@ -0,0 +1,226 @@
+//! Batched read of many heterogeneous variables packed into as few PDUs as
+//! the negotiated PDU length allows, instead of one PDU per variable. The
+//! read-side counterpart of [`super::batch_write`].
+
+use std::io::Cursor;
+use tokio::net::TcpStream;
+
+use super::header::S7ProtocolHeader;
+use super::proto::{ProtoRead, ProtoWrite};
+use super::types::{Area, DataItem, ReadWriteParams, RequestItem, S7DataTypes, READ_OPERATION};
+use crate::connection::config::ConnectionConfig;
+use crate::connection::retry::exchange_with_retry;
+use crate::errors::{Error, S7DataItemResponseError, S7ProtocolError};
+
+/// One variable to read as part of a batch: an address plus how many
+/// elements of `data_type` to pull starting at `start`.
+pub struct ReadItem {
+    pub area: Area,
+    pub db_number: u16,
+    pub start: u32,
+    pub data_type: S7DataTypes,
+    pub count: u16,
+}
+
+struct EncodedItem {
+    request_item_bytes: Vec<u8>,
+    /// Upper bound on the bytes this item contributes to the *response*
+    /// PDU: a return-code/transport-size/length header plus `count`
+    /// elements of `data_type`, word-aligned. This, not the (small, fixed)
+    /// request side, is usually what limits how many items fit in a batch —
+    /// a handful of `RequestItem`s are cheap to ask for, but the PLC answers
+    /// with all of their data at once.
+    response_item_len: usize,
+}
+
+fn encode_item(item: &ReadItem) -> EncodedItem {
+    let request_item = RequestItem::build(item.area, item.db_number, item.start, item.data_type, item.count);
+    let mut buf = Cursor::new(Vec::new());
+    request_item.encode(&mut buf);
+
+    const RESPONSE_ITEM_HEADER_LEN: usize = 4; // return_code (1) + transport_size (1) + length (2)
+    let data_len = item.count as usize * item.data_type.get_size() as usize;
+    let response_item_len = RESPONSE_ITEM_HEADER_LEN + data_len + (data_len % 2);
+
+    EncodedItem {
+        request_item_bytes: buf.into_inner(),
+        response_item_len,
+    }
+}
+
+/// Bytes an S7 header plus a bare `Read/Write Var` parameter block (function
+/// code + item count, no items yet) take on the wire, measured the same way
+/// [`super::batch_write::write_items`]'s overhead is: by actually encoding a
+/// probe. This is the request's fixed overhead; the response's is this plus
+/// [`ACK_ERROR_FIELDS_LEN`], since an ack's header also carries an error
+/// class and code.
+fn base_overhead() -> usize {
+    let mut pdu_reference = 0;
+    let header = S7ProtocolHeader::build_request(&mut pdu_reference, 0, 0);
+    let mut header_buf = Cursor::new(Vec::new());
+    header.encode(&mut header_buf);
+    const PARAMS_HEADER_LEN: usize = 2; // function_code (1) + item_count (1)
+    header_buf.get_ref().len() + PARAMS_HEADER_LEN
+}
+
+/// Bytes an ack-data header has beyond a job request's: error class (1) +
+/// error code (1).
+const ACK_ERROR_FIELDS_LEN: usize = 2;
+
+/// Greedily packs as many of `encoded[start..]` as fit in one PDU, bounded by
+/// *both* the request's size and the expected response's size (the response
+/// is usually bigger: each item's data, not just its address, comes back).
+/// Always includes at least one item so a single oversized request fails
+/// loudly in the exchange rather than stalling the loop in [`read_items`].
+/// Also caps a batch at 255 items: `item_count` is a single protocol byte,
+/// and a batch any larger would wrap silently when narrowed to `u8`.
+fn pack_batch(encoded: &[EncodedItem], start: usize, pdu_length: u16) -> usize {
+    const MAX_ITEMS_PER_BATCH: usize = 255;
+
+    let mut request_used = base_overhead();
+    let mut response_used = base_overhead() + ACK_ERROR_FIELDS_LEN;
+    let mut count = 0;
+    for item in &encoded[start..] {
+        if count >= MAX_ITEMS_PER_BATCH {
+            break;
+        }
+        let request_len = item.request_item_bytes.len();
+        let response_len = item.response_item_len;
+        if count > 0
+            && (request_used + request_len > pdu_length as usize
+                || response_used + response_len > pdu_length as usize)
+        {
+            break;
+        }
+        request_used += request_len;
+        response_used += response_len;
+        count += 1;
+    }
+    count
+}
+
+/// Reads many heterogeneous variables in as few PDUs as the negotiated
+/// `pdu_length` allows, transparently splitting across several when they
+/// don't all fit in one. Returns one result per input item, in the same
+/// order, so one failed variable doesn't mask the others' data.
+pub(crate) async fn read_items(
+    conn: &mut TcpStream,
+    pdu_length: u16,
+    pdu_number: &mut u16,
+    items: &[ReadItem],
+    config: &ConnectionConfig,
+) -> Result<Vec<Result<Vec<u8>, S7DataItemResponseError>>, Error> {
+    let encoded: Vec<EncodedItem> = items.iter().map(encode_item).collect();
+
+    let mut results = Vec::with_capacity(items.len());
+    let mut index = 0;
+    while index < encoded.len() {
+        let batch_len = pack_batch(&encoded, index, pdu_length);
+        let batch = &encoded[index..index + batch_len];
+
+        let mut params_buf = Cursor::new(Vec::new());
+        params_buf.write_u8(READ_OPERATION);
+        params_buf.write_u8(batch.len() as u8);
+        for item in batch {
+            params_buf.write_bytes(&item.request_item_bytes);
+        }
+
+        let param_length = params_buf.get_ref().len() as u16;
+
+        let exchanged_data = exchange_with_retry(conn, pdu_number, config, |pdu_reference| {
+            let mut pdu_reference = pdu_reference;
+            let s7_header = S7ProtocolHeader::build_request(&mut pdu_reference, param_length, 0);
+            let mut request_buf = Cursor::new(Vec::new());
+            s7_header.encode(&mut request_buf);
+            request_buf.write_bytes(params_buf.get_ref());
+            request_buf.into_inner()
+        })
+        .await?;
+        let mut response = Cursor::new(exchanged_data);
+
+        let header = S7ProtocolHeader::decode(&mut response)?
+            .is_ack()?
+            .is_current_pdu_response(*pdu_number)?;
+
+        if header.has_error() {
+            let (class, code) = header.get_errors();
+            return Err(Error::S7ProtocolError(S7ProtocolError::from_codes(
+                class, code,
+            )));
+        }
+
+        // A read acknowledgement's parameter block only echoes the function
+        // code/item count; every item's own outcome and payload live in the
+        // data part, in request order.
+        let _params = ReadWriteParams::decode(&mut response, header.param_length())?;
+        for (i, _) in batch.iter().enumerate() {
+            let item = DataItem::decode_read_item(&mut response, i == batch.len() - 1)?;
+            results.push(if item.error_code == 0xFF {
+                Ok(item.data)
+            } else {
+                Err(S7DataItemResponseError::from(item.error_code))
+            });
+        }
+
+        index += batch_len;
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item_of_len(request_len: usize, response_len: usize) -> EncodedItem {
+        EncodedItem {
+            request_item_bytes: vec![0; request_len],
+            response_item_len: response_len,
+        }
+    }
+
+    #[test]
+    fn packs_everything_into_one_batch_when_it_fits() {
+        let encoded = vec![item_of_len(12, 8), item_of_len(12, 8), item_of_len(12, 8)];
+        assert_eq!(pack_batch(&encoded, 0, 1000), 3);
+    }
+
+    #[test]
+    fn splits_across_batches_once_the_request_pdu_length_is_exceeded() {
+        let encoded = vec![item_of_len(12, 8), item_of_len(12, 8), item_of_len(12, 8)];
+        let pdu_length = (base_overhead() + 12 * 2 + 1) as u16;
+
+        let first_batch_len = pack_batch(&encoded, 0, pdu_length);
+        assert_eq!(first_batch_len, 2);
+
+        let second_batch_len = pack_batch(&encoded, first_batch_len, pdu_length);
+        assert_eq!(second_batch_len, 1);
+    }
+
+    #[test]
+    fn splits_once_the_expected_response_would_overflow_the_pdu_even_if_the_request_is_cheap() {
+        // Each item's own RequestItem is tiny, but it asks for a 100-byte DB
+        // read: three of these must not get packed into one PDU just because
+        // the *requests* are small.
+        let encoded = vec![item_of_len(12, 104), item_of_len(12, 104), item_of_len(12, 104)];
+        let pdu_length = (base_overhead() + ACK_ERROR_FIELDS_LEN + 104 * 2 + 1) as u16;
+
+        let first_batch_len = pack_batch(&encoded, 0, pdu_length);
+        assert_eq!(first_batch_len, 2);
+
+        let second_batch_len = pack_batch(&encoded, first_batch_len, pdu_length);
+        assert_eq!(second_batch_len, 1);
+    }
+
+    #[test]
+    fn caps_a_batch_at_255_items_even_if_more_would_fit_the_pdu() {
+        let encoded: Vec<_> = (0..300).map(|_| item_of_len(1, 1)).collect();
+        assert_eq!(pack_batch(&encoded, 0, u16::MAX), 255);
+    }
+
+    #[test]
+    fn always_includes_at_least_one_oversized_item() {
+        let encoded = vec![item_of_len(12, 4000)];
+        assert_eq!(pack_batch(&encoded, 0, 100), 1);
+    }
+}
@@ -0,0 +1,209 @@
+//! Batched write of many heterogeneous variables packed into as few PDUs as
+//! the negotiated PDU length allows, instead of one PDU per variable.
+
+use std::io::Cursor;
+use tokio::net::TcpStream;
+
+use super::header::S7ProtocolHeader;
+use super::proto::{ProtoRead, ProtoWrite};
+use super::types::{Area, DataItem, ReadWriteParams, RequestItem, S7DataTypes, WRITE_OPERATION};
+use crate::connection::config::ConnectionConfig;
+use crate::connection::retry::exchange_with_retry;
+use crate::errors::{Error, S7DataItemResponseError, S7ProtocolError};
+
+/// One variable to write as part of a batch: an address plus its payload.
+pub struct WriteItem {
+    pub area: Area,
+    pub db_number: u16,
+    pub start: u32,
+    pub data_type: S7DataTypes,
+    pub data: Vec<u8>,
+}
+
+struct EncodedItem {
+    request_item_bytes: Vec<u8>,
+    data_item: DataItem,
+    /// Upper bound on `data_item`'s encoded length, word-alignment fill byte
+    /// included. Only ever an overestimate (the item that actually lands
+    /// last in its batch skips the fill byte), which is fine for packing: it
+    /// can only make us split a batch one item earlier than strictly needed,
+    /// never overflow one.
+    data_item_len: usize,
+}
+
+fn encode_item(item: &WriteItem) -> Result<EncodedItem, Error> {
+    let count = item.data.len() as u32 / item.data_type.get_size();
+    let request_item = RequestItem::build(item.area, item.db_number, item.start, item.data_type, count as u16);
+    let mut request_item_buf = Cursor::new(Vec::new());
+    request_item.encode(&mut request_item_buf);
+
+    let data_item = DataItem::build_write(item.data_type.into(), Some(&item.data))?;
+    const DATA_ITEM_HEADER_LEN: usize = 3; // var_type (1) + count (2)
+    let data_item_len = DATA_ITEM_HEADER_LEN + item.data.len() + (item.data.len() % 2);
+
+    Ok(EncodedItem {
+        request_item_bytes: request_item_buf.into_inner(),
+        data_item,
+        data_item_len,
+    })
+}
+
+/// Bytes an S7 header plus a bare `Read/Write Var` parameter block (function
+/// code + item count, no items yet) take on the wire, measured the same way
+/// `write_area`'s single-item overhead is: by actually encoding a probe.
+fn base_overhead() -> usize {
+    let mut pdu_reference = 0;
+    let header = S7ProtocolHeader::build_request(&mut pdu_reference, 0, 0);
+    let mut header_buf = Cursor::new(Vec::new());
+    header.encode(&mut header_buf);
+    const PARAMS_HEADER_LEN: usize = 2; // function_code (1) + item_count (1)
+    header_buf.get_ref().len() + PARAMS_HEADER_LEN
+}
+
+/// Greedily packs as many of `encoded[start..]` as fit in one PDU, always
+/// including at least one item so a single oversized variable fails loudly
+/// in the exchange rather than stalling the loop in [`write_items`]. Also
+/// caps a batch at 255 items: `item_count` is a single protocol byte, and a
+/// batch any larger would wrap silently when narrowed to `u8`.
+fn pack_batch(encoded: &[EncodedItem], start: usize, pdu_length: u16) -> usize {
+    const MAX_ITEMS_PER_BATCH: usize = 255;
+
+    let mut used = base_overhead();
+    let mut count = 0;
+    for item in &encoded[start..] {
+        if count >= MAX_ITEMS_PER_BATCH {
+            break;
+        }
+        let item_len = item.request_item_bytes.len() + item.data_item_len;
+        if count > 0 && used + item_len > pdu_length as usize {
+            break;
+        }
+        used += item_len;
+        count += 1;
+    }
+    count
+}
+
+/// Writes many heterogeneous variables in as few PDUs as the negotiated
+/// `pdu_length` allows, transparently splitting across several when they
+/// don't all fit in one. Returns one result per input item, in the same
+/// order, so one failed variable doesn't mask the outcome of the others.
+pub(crate) async fn write_items(
+    conn: &mut TcpStream,
+    pdu_length: u16,
+    pdu_number: &mut u16,
+    items: &[WriteItem],
+    config: &ConnectionConfig,
+) -> Result<Vec<Result<(), S7DataItemResponseError>>, Error> {
+    let encoded = items.iter().map(encode_item).collect::<Result<Vec<_>, Error>>()?;
+
+    let mut results = Vec::with_capacity(items.len());
+    let mut index = 0;
+    while index < encoded.len() {
+        let batch_len = pack_batch(&encoded, index, pdu_length);
+        let batch = &encoded[index..index + batch_len];
+
+        let mut params_buf = Cursor::new(Vec::new());
+        params_buf.write_u8(WRITE_OPERATION);
+        params_buf.write_u8(batch.len() as u8);
+        for item in batch {
+            params_buf.write_bytes(&item.request_item_bytes);
+        }
+
+        let mut data_buf = Cursor::new(Vec::new());
+        for (i, item) in batch.iter().enumerate() {
+            item.data_item.encode(&mut data_buf, i == batch.len() - 1);
+        }
+
+        let param_length = params_buf.get_ref().len() as u16;
+        let data_length = data_buf.get_ref().len() as u16;
+
+        let exchanged_data = exchange_with_retry(conn, pdu_number, config, |pdu_reference| {
+            let mut pdu_reference = pdu_reference;
+            let s7_header =
+                S7ProtocolHeader::build_request(&mut pdu_reference, param_length, data_length);
+            let mut request_buf = Cursor::new(Vec::new());
+            s7_header.encode(&mut request_buf);
+            request_buf.write_bytes(params_buf.get_ref());
+            request_buf.write_bytes(data_buf.get_ref());
+            request_buf.into_inner()
+        })
+        .await?;
+        let mut response = Cursor::new(exchanged_data);
+
+        let header = S7ProtocolHeader::decode(&mut response)?
+            .is_ack()?
+            .is_current_pdu_response(*pdu_number)?;
+
+        if header.has_error() {
+            let (class, code) = header.get_errors();
+            return Err(Error::S7ProtocolError(S7ProtocolError::from_codes(
+                class, code,
+            )));
+        }
+
+        // A write acknowledgement's parameter block only echoes the function
+        // code/item count; every item's own outcome is its return code byte
+        // in the data part, in request order.
+        let _params = ReadWriteParams::decode(&mut response, header.param_length())?;
+        for _ in batch {
+            let error_code = DataItem::decode_write_result(&mut response)?;
+            results.push(if error_code == 0xFF {
+                Ok(())
+            } else {
+                Err(S7DataItemResponseError::from(error_code))
+            });
+        }
+
+        index += batch_len;
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item_of_len(request_len: usize, data_len: usize) -> EncodedItem {
+        use super::super::types::DataItemTransportSize;
+
+        let data = vec![0; data_len];
+        EncodedItem {
+            request_item_bytes: vec![0; request_len],
+            data_item_len: 3 + data.len() + (data.len() % 2),
+            data_item: DataItem::build_write(DataItemTransportSize::Byte, Some(&data)).unwrap(),
+        }
+    }
+
+    #[test]
+    fn packs_everything_into_one_batch_when_it_fits() {
+        let encoded = vec![item_of_len(10, 4), item_of_len(10, 4), item_of_len(10, 4)];
+        assert_eq!(pack_batch(&encoded, 0, 1000), 3);
+    }
+
+    #[test]
+    fn splits_across_batches_once_the_pdu_length_is_exceeded() {
+        // Each item is 10 (request) + 3 + 4 (data-item header + data) = 17 bytes.
+        let encoded = vec![item_of_len(10, 4), item_of_len(10, 4), item_of_len(10, 4)];
+        let pdu_length = (base_overhead() + 17 * 2 + 1) as u16;
+
+        let first_batch_len = pack_batch(&encoded, 0, pdu_length);
+        assert_eq!(first_batch_len, 2);
+
+        let second_batch_len = pack_batch(&encoded, first_batch_len, pdu_length);
+        assert_eq!(second_batch_len, 1);
+    }
+
+    #[test]
+    fn caps_a_batch_at_255_items_even_if_more_would_fit_the_pdu() {
+        let encoded: Vec<_> = (0..300).map(|_| item_of_len(1, 0)).collect();
+        assert_eq!(pack_batch(&encoded, 0, u16::MAX), 255);
+    }
+
+    #[test]
+    fn always_includes_at_least_one_oversized_item() {
+        let encoded = vec![item_of_len(10, 4000)];
+        assert_eq!(pack_batch(&encoded, 0, 100), 1);
+    }
+}
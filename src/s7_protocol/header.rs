@@ -0,0 +1,122 @@
+//! The S7 protocol header ("S7 header") that frames every job request and
+//! ack-data response, sitting on top of the TPKT/COTP transport.
+
+use crate::errors::{Error, IsoError};
+use crate::s7_protocol::proto::{ProtoRead, ProtoWrite};
+
+const PROTOCOL_ID: u8 = 0x32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RosCtr {
+    Job = 0x01,
+    AckData = 0x03,
+}
+
+impl RosCtr {
+    fn decode(value: u8) -> Result<Self, Error> {
+        match value {
+            0x01 => Ok(Self::Job),
+            0x03 => Ok(Self::AckData),
+            _ => Err(Error::ISOResponse(IsoError::InvalidPDU)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct S7ProtocolHeader {
+    rosctr: RosCtr,
+    pdu_reference: u16,
+    param_length: u16,
+    data_length: u16,
+    /// Only present on ack-data responses.
+    error_class: Option<u8>,
+    error_code: Option<u8>,
+}
+
+impl S7ProtocolHeader {
+    /// Builds the header for an outgoing job request against the current PDU
+    /// reference.
+    pub(crate) fn build_request(pdu_reference: &mut u16, param_length: u16, data_length: u16) -> Self {
+        Self {
+            rosctr: RosCtr::Job,
+            pdu_reference: *pdu_reference,
+            param_length,
+            data_length,
+            error_class: None,
+            error_code: None,
+        }
+    }
+
+    pub(crate) fn encode(&self, w: &mut impl ProtoWrite) {
+        w.write_u8(PROTOCOL_ID);
+        w.write_u8(self.rosctr as u8);
+        w.write_u16(0); // redundancy identification, always zero
+        w.write_u16(self.pdu_reference);
+        w.write_u16(self.param_length);
+        w.write_u16(self.data_length);
+        if let (Some(class), Some(code)) = (self.error_class, self.error_code) {
+            w.write_u8(class);
+            w.write_u8(code);
+        }
+    }
+
+    pub(crate) fn decode(r: &mut impl ProtoRead) -> Result<Self, Error> {
+        let protocol_id = r.read_u8()?;
+        if protocol_id != PROTOCOL_ID {
+            return Err(Error::ISOResponse(IsoError::InvalidPDU));
+        }
+        let rosctr = RosCtr::decode(r.read_u8()?)?;
+        let _redundancy_identification = r.read_u16()?;
+        let pdu_reference = r.read_u16()?;
+        let param_length = r.read_u16()?;
+        let data_length = r.read_u16()?;
+        let (error_class, error_code) = match rosctr {
+            RosCtr::AckData => (Some(r.read_u8()?), Some(r.read_u8()?)),
+            RosCtr::Job => (None, None),
+        };
+
+        if r.remaining() < param_length as usize + data_length as usize {
+            return Err(Error::ISOResponse(IsoError::ShortPacket));
+        }
+
+        Ok(Self {
+            rosctr,
+            pdu_reference,
+            param_length,
+            data_length,
+            error_class,
+            error_code,
+        })
+    }
+
+    pub(crate) fn is_ack(self) -> Result<Self, Error> {
+        match self.rosctr {
+            RosCtr::AckData => Ok(self),
+            RosCtr::Job => Err(Error::RequestNotAcknowledged),
+        }
+    }
+
+    pub(crate) fn is_current_pdu_response(self, pdu_reference: u16) -> Result<Self, Error> {
+        if self.pdu_reference == pdu_reference {
+            Ok(self)
+        } else {
+            Err(Error::ResponseDoesNotBelongToCurrentPDU)
+        }
+    }
+
+    pub(crate) fn has_error(&self) -> bool {
+        !matches!(self.error_class, None | Some(0x00))
+    }
+
+    pub(crate) fn get_errors(&self) -> (Option<u8>, Option<u8>) {
+        (self.error_class, self.error_code)
+    }
+
+    pub(crate) fn data_length(&self) -> u16 {
+        self.data_length
+    }
+
+    pub(crate) fn param_length(&self) -> u16 {
+        self.param_length
+    }
+}
@@ -0,0 +1,12 @@
+//! Encoding/decoding and wire types for the S7 communication protocol.
+
+mod batch_read;
+mod batch_write;
+pub(crate) mod header;
+pub(crate) mod proto;
+mod types;
+mod write_area;
+
+pub use batch_read::ReadItem;
+pub use batch_write::WriteItem;
+pub use types::{Area, S7DataTypes};
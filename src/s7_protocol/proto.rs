@@ -0,0 +1,109 @@
+//! Cursor-based big-endian codec used to encode/decode S7 PDUs.
+//!
+//! S7 (like the TPKT/COTP framing it rides on) puts every multi-byte field
+//! in network byte order, so `ProtoWrite`/`ProtoRead` only ever need to speak
+//! big-endian. Implementing them over a `std::io::Cursor<Vec<u8>>` gives
+//! every wire struct a single, bounds-checked place to serialize from and
+//! deserialize into, instead of each call site hand-computing offsets.
+
+use std::io::Cursor;
+
+use crate::errors::{Error, IsoError};
+
+/// Write big-endian primitives into a growing byte buffer.
+pub(crate) trait ProtoWrite {
+    fn write_u8(&mut self, value: u8);
+    fn write_u16(&mut self, value: u16);
+    fn write_u32(&mut self, value: u32);
+    fn write_bytes(&mut self, value: &[u8]);
+}
+
+/// Read big-endian primitives out of a byte buffer, failing cleanly once the
+/// cursor runs past the end of the data.
+pub(crate) trait ProtoRead {
+    fn read_u8(&mut self) -> Result<u8, Error>;
+    fn read_u16(&mut self) -> Result<u16, Error>;
+    fn read_u32(&mut self) -> Result<u32, Error>;
+    fn read_bytes(&mut self, len: usize) -> Result<Vec<u8>, Error>;
+
+    /// Bytes left between the current position and the end of the buffer.
+    fn remaining(&self) -> usize;
+}
+
+impl ProtoWrite for Cursor<Vec<u8>> {
+    fn write_u8(&mut self, value: u8) {
+        self.get_mut().push(value);
+    }
+
+    fn write_u16(&mut self, value: u16) {
+        self.get_mut().extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.get_mut().extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn write_bytes(&mut self, value: &[u8]) {
+        self.get_mut().extend_from_slice(value);
+    }
+}
+
+impl ProtoRead for Cursor<Vec<u8>> {
+    fn read_u8(&mut self) -> Result<u8, Error> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16, Error> {
+        let bytes = self.read_bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Error> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<Vec<u8>, Error> {
+        if self.remaining() < len {
+            return Err(Error::ISOResponse(IsoError::ShortPacket));
+        }
+        let pos = self.position() as usize;
+        let bytes = self.get_ref()[pos..pos + len].to_vec();
+        self.set_position((pos + len) as u64);
+        Ok(bytes)
+    }
+
+    fn remaining(&self) -> usize {
+        self.get_ref().len().saturating_sub(self.position() as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_primitives() {
+        let mut w = Cursor::new(Vec::new());
+        w.write_u8(0x01);
+        w.write_u16(0x0203);
+        w.write_u32(0x0405_0607);
+        w.write_bytes(&[0xAA, 0xBB]);
+
+        let mut r = Cursor::new(w.into_inner());
+        assert_eq!(r.read_u8().unwrap(), 0x01);
+        assert_eq!(r.read_u16().unwrap(), 0x0203);
+        assert_eq!(r.read_u32().unwrap(), 0x0405_0607);
+        assert_eq!(r.read_bytes(2).unwrap(), vec![0xAA, 0xBB]);
+    }
+
+    #[test]
+    fn read_past_end_is_a_short_packet() {
+        let mut r = Cursor::new(vec![0x01]);
+        let _ = r.read_u8().unwrap();
+        assert!(matches!(
+            r.read_u8(),
+            Err(Error::ISOResponse(IsoError::ShortPacket))
+        ));
+    }
+}
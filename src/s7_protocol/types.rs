@@ -0,0 +1,300 @@
+//! Wire types carried inside an S7 job/ack-data PDU: the read/write
+//! parameter block, the request items addressing PLC memory, and the data
+//! items carrying (or acknowledging) the payload.
+
+use crate::errors::{Error, IsoError};
+use crate::s7_protocol::proto::{ProtoRead, ProtoWrite};
+
+pub(crate) const READ_OPERATION: u8 = 0x04;
+pub(crate) const WRITE_OPERATION: u8 = 0x05;
+
+/// A memory area inside the PLC that can be addressed by a [`RequestItem`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Area {
+    ProcessInput = 0x81,
+    ProcessOutput = 0x82,
+    Merker = 0x83,
+    DataBlock = 0x84,
+    Counter = 0x1C,
+    Timer = 0x1D,
+}
+
+/// The elementary data type of a single value inside a PLC memory area.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum S7DataTypes {
+    Bit,
+    Byte,
+    Word,
+    DWord,
+    Real,
+}
+
+impl S7DataTypes {
+    /// Size, in bytes, of a single element of this type on the wire.
+    pub(crate) fn get_size(&self) -> u32 {
+        match self {
+            Self::Bit | Self::Byte => 1,
+            Self::Word => 2,
+            Self::DWord | Self::Real => 4,
+        }
+    }
+
+    /// The "transport size" code carried in a [`RequestItem`]'s address
+    /// specification.
+    fn wire_type(&self) -> u8 {
+        match self {
+            Self::Bit => 0x01,
+            Self::Byte => 0x02,
+            Self::Word => 0x04,
+            Self::DWord => 0x06,
+            Self::Real => 0x08,
+        }
+    }
+}
+
+/// The transport size a [`DataItem`] is encoded with, independent of the
+/// elementary type that was requested: the PLC always returns/accepts either
+/// a bit or a generic byte string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DataItemTransportSize {
+    Bit = 0x03,
+    Byte = 0x04,
+}
+
+impl DataItemTransportSize {
+    /// Multiplier turning an element count into the `count` field of a
+    /// [`DataItem`], which is in bits for both: one per element for
+    /// [`Self::Bit`], eight per byte for [`Self::Byte`].
+    pub(crate) fn len(&self) -> u16 {
+        match self {
+            Self::Bit => 1,
+            Self::Byte => 8,
+        }
+    }
+
+}
+
+impl From<S7DataTypes> for DataItemTransportSize {
+    fn from(value: S7DataTypes) -> Self {
+        match value {
+            S7DataTypes::Bit => Self::Bit,
+            S7DataTypes::Byte | S7DataTypes::Word | S7DataTypes::DWord | S7DataTypes::Real => {
+                Self::Byte
+            }
+        }
+    }
+}
+
+/// The `Read/Write Var` parameter block: a function code followed by the
+/// request items it addresses.
+pub(crate) struct ReadWriteParams {
+    pub(crate) function_code: u8,
+    pub(crate) item_count: u8,
+    pub(crate) request_item: Option<Vec<RequestItem>>,
+}
+
+impl ReadWriteParams {
+    pub(crate) fn build_read(items: Vec<RequestItem>) -> Self {
+        Self {
+            function_code: READ_OPERATION,
+            item_count: items.len() as u8,
+            request_item: Some(items),
+        }
+    }
+
+    pub(crate) fn build_write(items: Vec<RequestItem>) -> Self {
+        Self {
+            function_code: WRITE_OPERATION,
+            item_count: items.len() as u8,
+            request_item: Some(items),
+        }
+    }
+
+    pub(crate) fn encode(&self, w: &mut impl ProtoWrite) {
+        w.write_u8(self.function_code);
+        w.write_u8(self.item_count);
+        if let Some(items) = &self.request_item {
+            for item in items {
+                item.encode(w);
+            }
+        }
+    }
+
+    /// Decodes the parameter block of a response, given the `param_length`
+    /// declared in the S7 header. Only the leading function code/item count
+    /// is meaningful on a write acknowledgement; request items are never
+    /// echoed back.
+    pub(crate) fn decode(r: &mut impl ProtoRead, param_length: u16) -> Result<Self, Error> {
+        if (param_length as usize) < 2 {
+            return Err(Error::ISOResponse(IsoError::ShortPacket));
+        }
+        Ok(Self {
+            function_code: r.read_u8()?,
+            item_count: r.read_u8()?,
+            request_item: None,
+        })
+    }
+}
+
+/// Addresses a contiguous run of elements inside a PLC [`Area`].
+pub(crate) struct RequestItem {
+    area: Area,
+    db_number: u16,
+    start: u32,
+    data_type: S7DataTypes,
+    count: u16,
+}
+
+impl RequestItem {
+    pub(crate) fn build(
+        area: Area,
+        db_number: u16,
+        start: u32,
+        data_type: S7DataTypes,
+        count: u16,
+    ) -> Self {
+        Self {
+            area,
+            db_number,
+            start,
+            data_type,
+            count,
+        }
+    }
+
+    pub(crate) fn encode(&self, w: &mut impl ProtoWrite) {
+        w.write_u8(0x12); // specification type: variable addressing
+        w.write_u8(0x0A); // length of the address specification that follows
+        w.write_u8(0x10); // syntax id: S7ANY
+        w.write_u8(self.data_type.wire_type());
+        w.write_u16(self.count);
+        w.write_u16(self.db_number);
+        w.write_u8(self.area as u8);
+        // 24-bit address: byte offset in the upper 21 bits, bit offset in the low 3.
+        let address = self.start << 3;
+        w.write_bytes(&address.to_be_bytes()[1..]);
+    }
+}
+
+/// A single value (or, on a write acknowledgement, a single return code)
+/// carried in the data part of a PDU.
+pub(crate) struct DataItem {
+    pub(crate) error_code: u8,
+    pub(crate) var_type: u8,
+    pub(crate) count: u16,
+    pub(crate) data: Vec<u8>,
+}
+
+impl DataItem {
+    pub(crate) fn build_write(
+        data_type: DataItemTransportSize,
+        data: Option<&[u8]>,
+    ) -> Result<Self, Error> {
+        let transport_size = data_type.len();
+        match data {
+            Some(vec) => Ok(Self {
+                error_code: 0,
+                var_type: data_type as u8,
+                count: vec.len() as u16 * transport_size,
+                data: vec.to_vec(),
+            }),
+            None => Err(Error::ISORequest(IsoError::InvalidDataSize)),
+        }
+    }
+
+    /// Encodes this item's `var_type`/`count`/`data`, word-aligning with a
+    /// fill byte when `data` has odd length — unless it's the last (or only)
+    /// item of the PDU, which the real wire format never pads, matching the
+    /// `is_last` exception [`Self::decode_read_item`] already makes on the
+    /// read side.
+    pub(crate) fn encode(&self, w: &mut impl ProtoWrite, is_last: bool) {
+        w.write_u8(self.var_type);
+        w.write_u16(self.count);
+        w.write_bytes(&self.data);
+        if !is_last && self.data.len() % 2 != 0 {
+            w.write_u8(0); // fill byte: data part is always word-aligned
+        }
+    }
+
+    /// Decodes a single return code from a `Write Var` acknowledgement: one
+    /// byte per item, with no transport size/length/data following it.
+    pub(crate) fn decode_write_result(r: &mut impl ProtoRead) -> Result<u8, Error> {
+        r.read_u8()
+    }
+
+    /// Decodes a single item of a `Read Var` acknowledgement: a return code,
+    /// then (regardless of whether that return code signals success) a
+    /// transport size and a length, which together with `is_last` tell us
+    /// whether a word-alignment fill byte follows the data.
+    ///
+    /// `length` is carried in bits for [`DataItemTransportSize::Byte`] items
+    /// (the PLC's generic transport for byte/word/dword/real values) and in
+    /// bits directly for [`DataItemTransportSize::Bit`] items, where it's
+    /// always `1`.
+    pub(crate) fn decode_read_item(r: &mut impl ProtoRead, is_last: bool) -> Result<Self, Error> {
+        let error_code = r.read_u8()?;
+        let var_type = r.read_u8()?;
+        let count = r.read_u16()?;
+        let byte_len = if var_type == DataItemTransportSize::Bit as u8 {
+            count as usize
+        } else {
+            (count as usize).div_ceil(8)
+        };
+        let data = r.read_bytes(byte_len)?;
+        if !is_last && byte_len % 2 != 0 {
+            let _ = r.read_u8()?; // fill byte: data part is always word-aligned
+        }
+        Ok(Self {
+            error_code,
+            var_type,
+            count,
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn decodes_consecutive_items_skipping_the_fill_byte_between_odd_length_ones() {
+        let mut w = Cursor::new(Vec::new());
+        // Item 0: Byte transport, 3 bytes of data (odd -> fill byte follows
+        // since it isn't the last item).
+        w.write_u8(0xFF);
+        w.write_u8(DataItemTransportSize::Byte as u8);
+        w.write_u16(3 * 8);
+        w.write_bytes(&[0x01, 0x02, 0x03]);
+        w.write_u8(0x00); // fill byte
+
+        // Item 1 (last): Bit transport, a single bit, no fill byte.
+        w.write_u8(0xFF);
+        w.write_u8(DataItemTransportSize::Bit as u8);
+        w.write_u16(1);
+        w.write_bytes(&[0x01]);
+
+        let mut r = Cursor::new(w.into_inner());
+        let first = DataItem::decode_read_item(&mut r, false).unwrap();
+        assert_eq!(first.data, vec![0x01, 0x02, 0x03]);
+
+        let second = DataItem::decode_read_item(&mut r, true).unwrap();
+        assert_eq!(second.data, vec![0x01]);
+        assert_eq!(r.remaining(), 0);
+    }
+
+    #[test]
+    fn a_failed_item_surfaces_its_return_code() {
+        let mut w = Cursor::new(Vec::new());
+        w.write_u8(0x05); // AddressOutOfRange
+        w.write_u8(DataItemTransportSize::Byte as u8);
+        w.write_u16(0);
+
+        let mut r = Cursor::new(w.into_inner());
+        let item = DataItem::decode_read_item(&mut r, true).unwrap();
+        assert_eq!(item.error_code, 0x05);
+        assert!(item.data.is_empty());
+    }
+}
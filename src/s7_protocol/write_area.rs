@@ -1,39 +1,31 @@
-use std::convert::TryFrom;
-use std::mem;
-// use std::net::TcpStream;
+use std::io::Cursor;
 use tokio::net::TcpStream;
 
 use super::header::S7ProtocolHeader;
-use super::types::{
-    Area, DataItem, DataItemTransportSize, ReadWriteParams, RequestItem, S7DataTypes,
-    WRITE_OPERATION,
-};
-use crate::connection::tcp::exchange_buffer;
+use super::proto::{ProtoRead, ProtoWrite};
+use super::types::{Area, DataItem, ReadWriteParams, RequestItem, S7DataTypes};
+use crate::connection::config::ConnectionConfig;
+use crate::connection::retry::exchange_with_retry;
 use crate::errors::{Error, IsoError, S7DataItemResponseError, S7ProtocolError};
 
-impl ReadWriteParams {
-    fn build_write(items: Vec<RequestItem>) -> Self {
-        Self {
-            function_code: WRITE_OPERATION,
-            item_count: items.len() as u8,
-            request_item: Some(items),
-        }
-    }
-}
+/// Everything but the payload of a single-item write request: the S7 header
+/// plus the parameter block's `Read/Write Var` item and the data item's own
+/// `var_type`/`count` fields. Measured by actually encoding a zero-length
+/// probe request rather than hard-coded struct-size arithmetic, so it stays
+/// correct if the wire format ever grows a field.
+fn request_overhead(area: Area, db_number: u16, start: u32, data_type: S7DataTypes) -> usize {
+    let probe_item = RequestItem::build(area, db_number, start, data_type, 0);
+    let params = ReadWriteParams::build_write(vec![probe_item]);
+    let mut params_buf = Cursor::new(Vec::new());
+    params.encode(&mut params_buf);
 
-impl DataItem {
-    fn build_write(data_type: DataItemTransportSize, data: Option<&[u8]>) -> Result<Self, Error> {
-        let transport_size = data_type.len();
-        match data {
-            Some(vec) => Ok(Self {
-                error_code: 0,
-                var_type: data_type as u8,
-                count: vec.len() as u16 * transport_size,
-                data: vec.to_vec(),
-            }),
-            None => Err(Error::ISORequest(IsoError::InvalidDataSize)),
-        }
-    }
+    let mut pdu_reference = 0;
+    let header = S7ProtocolHeader::build_request(&mut pdu_reference, 0, 0);
+    let mut header_buf = Cursor::new(Vec::new());
+    header.encode(&mut header_buf);
+
+    const DATA_ITEM_FIELD_HEADER_LEN: usize = 3; // var_type (1) + count (2)
+    header_buf.get_ref().len() + params_buf.get_ref().len() + DATA_ITEM_FIELD_HEADER_LEN
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -45,79 +37,80 @@ pub(crate) async fn write_area(
     db_number: u16,
     start: u32,
     data_type: S7DataTypes,
-    buffer: &Vec<u8>,
+    buffer: &[u8],
+    config: &ConnectionConfig,
 ) -> Result<(), Error> {
     // Each packet cannot exceed the PDU length (in bytes) negotiated, and moreover
-    // we must ensure to transfer a "finite" number of item per PDU
-    // Reply telegram header (should be 35)
-    let header_size = (mem::size_of::<S7ProtocolHeader>() - 2) - 6 // -2 without first two fields;  -6 to account for options
-                        + (mem::size_of::<ReadWriteParams>())
-        - 3;
-    let requested_size = buffer.len() as u32 / data_type.get_size();
-    if ((pdu_length as i32 - header_size as i32) / data_type.get_size() as i32) < 1 {
+    // we must ensure to transfer a "finite" number of items per PDU.
+    let overhead = request_overhead(area, db_number, start, data_type);
+    if ((pdu_length as i64 - overhead as i64) / data_type.get_size() as i64) < 1 {
         return Err(Error::ISORequest(IsoError::InvalidPDU));
     }
-    let max_elements = (pdu_length as usize - header_size) as u32 / data_type.get_size();
+    let max_elements = (pdu_length as usize - overhead) as u32 / data_type.get_size();
+    let element_count = buffer.len() as u32 / data_type.get_size();
 
     let mut offset: u32 = 0;
-    while offset == 0 || offset < buffer.len() as u32 {
-        let items_to_write: u32 = match buffer.len() as u32 - offset {
-            x if x > max_elements => max_elements,
-            _ => requested_size - offset,
-        };
+    while offset == 0 || offset < element_count {
+        let items_to_write = (element_count - offset).min(max_elements);
+        let byte_offset = (offset * data_type.get_size()) as usize;
+        let byte_len = (items_to_write * data_type.get_size()) as usize;
 
-        let items = RequestItem::build(
+        let request_item = RequestItem::build(
             area,
             db_number,
             start + offset,
             data_type,
             items_to_write as u16,
         );
-        let mut data: Vec<u8> = DataItem::build_write(
-            data_type.into(),
-            buffer.get(offset as usize..(items_to_write * data_type.get_size()) as usize),
-        )?
-        .into();
-        let data_length = data.len();
+        let data_item =
+            DataItem::build_write(data_type.into(), buffer.get(byte_offset..byte_offset + byte_len))?;
 
-        let write_params = ReadWriteParams::build_write(vec![items]);
-        let mut write_params_u8: Vec<u8> = write_params.into();
-        write_params_u8.append(&mut data);
+        let write_params = ReadWriteParams::build_write(vec![request_item]);
+        let mut params_buf = Cursor::new(Vec::new());
+        write_params.encode(&mut params_buf);
+        let mut data_buf = Cursor::new(Vec::new());
+        data_item.encode(&mut data_buf, true); // always the only (and so last) item
+        let param_length = params_buf.get_ref().len() as u16;
+        let data_length = data_buf.get_ref().len() as u16;
 
-        let s7_header = S7ProtocolHeader::build_request(
-            pdu_number,
-            (write_params_u8.len() - data_length) as u16,
-            data_length as u16,
-        );
-        let mut request: Vec<u8> = s7_header.into();
-        request.append(&mut write_params_u8);
-
-        offset += requested_size;
-
-        let exchanged_data = exchange_buffer(conn, &mut request).await?;
-        let response = S7ProtocolHeader::try_from(exchanged_data[0..12].to_vec())?;
+        let exchanged_data = exchange_with_retry(conn, pdu_number, config, |pdu_reference| {
+            let mut pdu_reference = pdu_reference;
+            let s7_header =
+                S7ProtocolHeader::build_request(&mut pdu_reference, param_length, data_length);
+            let mut request_buf = Cursor::new(Vec::new());
+            s7_header.encode(&mut request_buf);
+            request_buf.write_bytes(params_buf.get_ref());
+            request_buf.write_bytes(data_buf.get_ref());
+            request_buf.into_inner()
+        })
+        .await?;
+        let mut response = Cursor::new(exchanged_data);
 
-        // check if response is acknowledged and pdu ref matches request pdu
-        let response = response.is_ack()?.is_current_pdu_response(*pdu_number)?;
+        let header = S7ProtocolHeader::decode(&mut response)?
+            .is_ack()?
+            .is_current_pdu_response(*pdu_number)?;
 
         // Check for errors
-        if response.has_error() {
-            let (class, code) = response.get_errors();
+        if header.has_error() {
+            let (class, code) = header.get_errors();
             return Err(Error::S7ProtocolError(S7ProtocolError::from_codes(
                 class, code,
             )));
         }
-        // Check for errors in data item
-        if let Some(&error_code) = exchanged_data.get(14) {
-            // 255 signals everything went alright
-            if error_code != 255 {
-                return Err(Error::DataItemError(S7DataItemResponseError::from(
-                    error_code,
-                )));
-            }
+
+        // A write acknowledgement's parameter block is just the echoed
+        // function code/item count; the real per-item outcome lives in the
+        // data part as a single return code byte.
+        let _params = ReadWriteParams::decode(&mut response, header.param_length())?;
+        let error_code = DataItem::decode_write_result(&mut response)?;
+        if error_code != 0xFF {
+            // 0xFF signals everything went alright
+            return Err(Error::DataItemError(S7DataItemResponseError::from(
+                error_code,
+            )));
         }
 
-        offset += requested_size;
+        offset += items_to_write;
     }
 
     Ok(())